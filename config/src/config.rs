@@ -20,6 +20,10 @@ use serde::{
     Serializer,
 };
 use smart_default::SmartDefault;
+use time::{
+    Duration,
+    OffsetDateTime,
+};
 
 use crate::error::{
     Error,
@@ -49,6 +53,13 @@ pub struct Config {
         serde(serialize_with = "ordered_map")
     )]
     pub loggers:   HashMap<Target, Logger>,
+    /// When `true`, the Handle-build path expands `$VAR`/`${VAR}`
+    /// references (and a leading `~`) in every `File`/`RollingFile`
+    /// appender's `path` using the current process environment, via
+    /// [`Config::expand_appender_paths`]. Defaults to `false` so existing
+    /// configs keep behaving exactly as before unless they opt in.
+    #[cfg_attr(feature = "serde", serde(default, rename = "expandPaths"))]
+    pub expand_paths: bool,
 }
 
 /// # Errors
@@ -95,17 +106,192 @@ impl Config {
 
         Config {
             default:   Logger {
-                level:     LevelFilter::INFO,
-                appenders: hset! { "stdout" },
-                format:    Format::default(),
+                level:        LevelFilter::INFO,
+                appenders:    hset! { "stdout" },
+                format:       Format::default(),
+                timestamp:    TimestampFormat::default(),
+                field_names:  FieldNames::default(),
+                level_casing: Casing::default(),
             },
             loggers:   hmap! {},
             appenders: hmap! {
-                "stdout" => Appender::Console
+                "stdout" => Appender::Console { ansi: false }
             },
+            expand_paths: false,
         }
     }
+
+    /// Expands `$VAR`/`${VAR}` references (and a leading `~`) in every
+    /// `File`/`RollingFile` appender's `path`, using the current process
+    /// environment. The Handle-build path calls this automatically when
+    /// `expand_paths` is `true`; callers building a `Handle` directly from a
+    /// `Config` value without going through that path must invoke it
+    /// themselves.
+    ///
+    /// # Errors
+    /// Returns an error if a referenced environment variable is unset.
+    pub fn expand_appender_paths(&mut self) -> Result<()> {
+        for appender in self.appenders.values_mut() {
+            match appender {
+                Appender::File { path } | Appender::RollingFile { path, .. } => {
+                    *path = expand_env_vars(path)?;
+                },
+                Appender::Null | Appender::Console { .. } => {},
+            }
+        }
+        Ok(())
+    }
 }
+
+/// Expands `$VAR`, `${VAR}`, and a leading `~` (to `$HOME`) in `path`.
+///
+/// # Errors
+/// Returns an error if a referenced environment variable is unset.
+fn expand_env_vars(path: &str) -> Result<String> {
+    let path = if let Some(rest) = path.strip_prefix('~') {
+        let home = std::env::var("HOME").map_err(|_| Error::EnvVarNotSet("HOME".to_string()))?;
+        Cow::Owned(format!("{home}{rest}"))
+    } else {
+        Cow::Borrowed(path)
+    };
+
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+
+        let mut name = String::new();
+        if braced {
+            for c in chars.by_ref() {
+                if c == '}' {
+                    break;
+                }
+                name.push(c);
+            }
+        } else if matches!(chars.peek(), Some(c) if c.is_alphabetic() || *c == '_') {
+            while matches!(chars.peek(), Some(c) if c.is_alphanumeric() || *c == '_') {
+                name.push(chars.next().unwrap_or_default());
+            }
+        }
+
+        // A bare `$` not followed by an identifier, or an empty `${}`, is not
+        // a variable reference -- treat it as literal text, the way a shell
+        // would, instead of failing to look up an empty variable name.
+        if name.is_empty() {
+            if braced {
+                out.push_str("${}");
+            } else {
+                out.push('$');
+            }
+            continue;
+        }
+
+        let value = std::env::var(&name).map_err(|_| Error::EnvVarNotSet(name))?;
+        out.push_str(&value);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod expand_paths_tests {
+    use super::*;
+
+    // env::set_var/remove_var are process-global; serialize these tests via
+    // a distinct variable name per test so they don't race each other.
+
+    #[test]
+    fn expands_a_braced_var() {
+        std::env::set_var("TRACE4RS_TEST_BRACED", "/var/log");
+        let result = expand_env_vars("${TRACE4RS_TEST_BRACED}/app.log").unwrap();
+        std::env::remove_var("TRACE4RS_TEST_BRACED");
+        assert_eq!(result, "/var/log/app.log");
+    }
+
+    #[test]
+    fn expands_an_unbraced_var_stopping_at_a_non_identifier_char() {
+        std::env::set_var("TRACE4RS_TEST_UNBRACED", "foo");
+        let result = expand_env_vars("$TRACE4RS_TEST_UNBRACED.log").unwrap();
+        std::env::remove_var("TRACE4RS_TEST_UNBRACED");
+        assert_eq!(result, "foo.log");
+    }
+
+    #[test]
+    fn expands_a_leading_tilde_to_home() {
+        std::env::set_var("HOME", "/home/trace4rs");
+        let result = expand_env_vars("~/app.log").unwrap();
+        assert_eq!(result, "/home/trace4rs/app.log");
+    }
+
+    #[test]
+    fn errors_on_an_unset_variable() {
+        std::env::remove_var("TRACE4RS_TEST_UNSET");
+        assert!(expand_env_vars("$TRACE4RS_TEST_UNSET/app.log").is_err());
+    }
+
+    #[test]
+    fn a_bare_dollar_not_followed_by_an_identifier_is_literal() {
+        let result = expand_env_vars("price: $5/app.log").unwrap();
+        assert_eq!(result, "price: $5/app.log");
+    }
+
+    #[test]
+    fn a_trailing_bare_dollar_is_literal() {
+        let result = expand_env_vars("app.log$").unwrap();
+        assert_eq!(result, "app.log$");
+    }
+
+    #[test]
+    fn an_empty_braced_reference_is_literal() {
+        let result = expand_env_vars("app${}.log").unwrap();
+        assert_eq!(result, "app${}.log");
+    }
+
+    #[test]
+    fn expand_appender_paths_expands_every_file_and_rolling_file_appender() {
+        std::env::set_var("TRACE4RS_TEST_LOGDIR", "/var/log/myapp");
+        let mut config = Config {
+            expand_paths: true,
+            appenders: literally::hmap! {
+                "file" => Appender::file("${TRACE4RS_TEST_LOGDIR}/app.log"),
+                "rolling" => Appender::RollingFile {
+                    path: "${TRACE4RS_TEST_LOGDIR}/rolling.log".to_string(),
+                    policy: Policy {
+                        maximum_file_size: "10mb".to_string(),
+                        max_size_roll_backups: 1,
+                        pattern: None,
+                        time_trigger: None,
+                    },
+                },
+                "console" => Appender::console(),
+            },
+            ..Config::console_config()
+        };
+
+        config.expand_appender_paths().unwrap();
+        std::env::remove_var("TRACE4RS_TEST_LOGDIR");
+
+        assert_eq!(
+            config.appenders.get(&AppenderId::from("file")),
+            Some(&Appender::file("/var/log/myapp/app.log"))
+        );
+        let Some(Appender::RollingFile { path, .. }) =
+            config.appenders.get(&AppenderId::from("rolling"))
+        else {
+            panic!("expected a RollingFile appender");
+        };
+        assert_eq!(path, "/var/log/myapp/rolling.log");
+    }
+}
+
 /// A log target, for example to capture all log messages in `trace4rs::config`
 /// the target would be `trace4rs::config`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -145,13 +331,42 @@ pub struct Logger {
         feature = "in-order-serialization",
         serde(serialize_with = "ordered_set")
     )]
-    pub appenders: HashSet<AppenderId>,
-    pub level:     LevelFilter,
+    pub appenders:    HashSet<AppenderId>,
+    pub level:        LevelFilter,
     #[cfg_attr(
         feature = "serde",
         serde(default = "Format::default", skip_serializing_if = "Format::is_normal")
     )]
-    pub format:    Format,
+    pub format:       Format,
+    /// Controls how the timestamp recorded on each event is rendered.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "TimestampFormat::default",
+            skip_serializing_if = "TimestampFormat::is_rfc3339"
+        )
+    )]
+    pub timestamp:    TimestampFormat,
+    /// The JSON key names used by the `Json` format. Defaults match the
+    /// output `trace4rs` has always used.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "FieldNames::default",
+            skip_serializing_if = "FieldNames::is_default"
+        )
+    )]
+    pub field_names:  FieldNames,
+    /// The casing used to render the level value in the `Json` and `Custom`
+    /// formats.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "Casing::default",
+            skip_serializing_if = "Casing::is_uppercase"
+        )
+    )]
+    pub level_casing: Casing,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, SmartDefault)]
@@ -165,6 +380,9 @@ pub enum Format {
     #[default]
     Normal,
     MessageOnly,
+    /// Emits one JSON object per event (newline-delimited), suitable for
+    /// feeding directly into log pipelines that expect NDJSON.
+    Json,
     Custom(String),
 }
 impl Format {
@@ -175,6 +393,92 @@ impl Format {
     }
 }
 
+/// Selects how an event's timestamp is rendered.
+#[derive(PartialEq, Eq, Clone, Debug, SmartDefault)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum TimestampFormat {
+    /// RFC 3339, e.g. `2023-08-01T12:34:56.789Z`. This is the default.
+    #[default]
+    Rfc3339,
+    /// Seconds since the Unix epoch, rendered as a bare integer.
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch, rendered as a bare integer.
+    UnixMillis,
+    /// A `time` crate format description in its version 2 syntax (e.g.
+    /// `[year]-[month]-[day]`), compiled once when the logger is built.
+    Custom(String),
+}
+impl TimestampFormat {
+    #[cfg(feature = "serde")]
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_rfc3339(&self) -> bool {
+        matches!(self, Self::Rfc3339)
+    }
+}
+
+/// Overrides for the JSON key names written by the `Json` format. Defaults
+/// are `timestamp`, `level`, `target` and `message`. The `Custom` format
+/// does not use these names.
+#[derive(PartialEq, Eq, Clone, Debug, SmartDefault)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct FieldNames {
+    #[default(String::from("timestamp"))]
+    pub timestamp: String,
+    #[default(String::from("level"))]
+    pub level:     String,
+    #[default(String::from("target"))]
+    pub target:    String,
+    #[default(String::from("message"))]
+    pub message:   String,
+}
+impl FieldNames {
+    #[cfg(feature = "serde")]
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The casing used when rendering the level value in the `Json` and
+/// `Custom` formats.
+#[derive(Copy, PartialEq, Eq, Clone, Debug, SmartDefault)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum Casing {
+    Lowercase,
+    /// Matches the level text `tracing` itself renders. This is the default.
+    #[default]
+    Uppercase,
+}
+impl Casing {
+    #[cfg(feature = "serde")]
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_uppercase(&self) -> bool {
+        matches!(self, Self::Uppercase)
+    }
+
+    #[must_use]
+    pub fn apply(self, s: &str) -> String {
+        match self {
+            Self::Lowercase => s.to_ascii_lowercase(),
+            Self::Uppercase => s.to_ascii_uppercase(),
+        }
+    }
+}
+
 /// Simply a wrapper around `tracing::LevelFilter` such that it can be used by
 /// `serde`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -241,7 +545,16 @@ impl FromStr for LevelFilter {
 )]
 pub enum Appender {
     Null,
-    Console,
+    Console {
+        /// Colorize the level token by its severity (red=ERROR, yellow=WARN,
+        /// green=INFO, blue=DEBUG, dim=TRACE) in the `Custom` format. The
+        /// `Normal` format instead uses `tracing-subscriber`'s own ANSI
+        /// styling, which also styles parts of the line other than the level.
+        /// Off by default, since most consumers of file-based config run in
+        /// non-interactive contexts.
+        #[cfg_attr(feature = "serde", serde(default))]
+        ansi: bool,
+    },
     File {
         path: String,
     },
@@ -258,7 +571,7 @@ impl Appender {
     }
 
     pub fn console() -> Self {
-        Self::Console
+        Self::Console { ansi: false }
     }
 }
 impl From<&str> for AppenderId {
@@ -283,6 +596,65 @@ pub struct Policy {
         serde(default, skip_serializing_if = "Option::is_none")
     )]
     pub pattern: Option<String>,
+    /// Rolls the file whenever the wall clock crosses this boundary, in
+    /// addition to (not instead of) `maximum_file_size`: whichever fires
+    /// first triggers the roll.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            rename = "timeTrigger",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub time_trigger: Option<TimeTrigger>,
+}
+
+/// A wall-clock boundary a `RollingFile` appender can roll on, in addition
+/// to size-based rolling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TimeTrigger {
+    Minutely,
+    Hourly,
+    Daily,
+}
+
+impl TimeTrigger {
+    /// Returns the next wall-clock boundary strictly after `from` that this
+    /// trigger fires on, e.g. for `Hourly` the start of the next hour. A
+    /// `from` that already sits exactly on a boundary still steps forward a
+    /// full unit, since `from` is always a file's first-write instant and a
+    /// roll is never due at the instant a file is created.
+    #[must_use]
+    pub fn next_boundary(self, from: OffsetDateTime) -> OffsetDateTime {
+        let truncated = match self {
+            Self::Minutely => from.replace_second(0).and_then(|d| d.replace_nanosecond(0)),
+            Self::Hourly => from
+                .replace_minute(0)
+                .and_then(|d| d.replace_second(0))
+                .and_then(|d| d.replace_nanosecond(0)),
+            Self::Daily => from
+                .replace_hour(0)
+                .and_then(|d| d.replace_minute(0))
+                .and_then(|d| d.replace_second(0))
+                .and_then(|d| d.replace_nanosecond(0)),
+        }
+        .expect("replacing with an in-range, constant value cannot fail");
+
+        let step = match self {
+            Self::Minutely => Duration::minutes(1),
+            Self::Hourly => Duration::hours(1),
+            Self::Daily => Duration::days(1),
+        };
+
+        truncated + step
+    }
 }
 
 impl Policy {
@@ -351,4 +723,68 @@ impl Policy {
             None => Err(Error::Overflow { number, unit }),
         }
     }
+
+    /// Returns whether a file first written at `first_write` should be
+    /// rolled given the current time `now`, per this policy's
+    /// `time_trigger`. Returns `false` if no `time_trigger` is configured.
+    ///
+    /// This only evaluates the time-based trigger; callers must still check
+    /// `maximum_file_size` independently and roll if either condition fires.
+    #[must_use]
+    pub fn should_roll_for_time(&self, first_write: OffsetDateTime, now: OffsetDateTime) -> bool {
+        self.time_trigger
+            .map_or(false, |trigger| now >= trigger.next_boundary(first_write))
+    }
+}
+
+#[cfg(test)]
+mod time_trigger_tests {
+    use super::*;
+
+    fn at(unix_seconds: i64, nanos: u32) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(unix_seconds).unwrap() + Duration::nanoseconds(nanos.into())
+    }
+
+    #[test]
+    fn minutely_boundary_is_the_start_of_the_next_minute() {
+        let from = at(61, 500); // 00:01:01.0000005
+        assert_eq!(TimeTrigger::Minutely.next_boundary(from), at(120, 0)); // 00:02:00
+    }
+
+    #[test]
+    fn hourly_boundary_steps_forward_even_when_from_is_exactly_on_a_boundary() {
+        let from = at(3600, 0); // exactly 01:00:00
+        assert_eq!(TimeTrigger::Hourly.next_boundary(from), at(7200, 0)); // 02:00:00
+    }
+
+    #[test]
+    fn daily_boundary_truncates_the_time_of_day() {
+        let from = at(100_000, 0); // some time on day 2 (not midnight)
+        let next = TimeTrigger::Daily.next_boundary(from);
+        assert_eq!(next, at(172_800, 0)); // midnight, day 3
+    }
+
+    #[test]
+    fn should_roll_for_time_is_false_without_a_time_trigger() {
+        let policy = Policy {
+            maximum_file_size: "10mb".to_string(),
+            max_size_roll_backups: 1,
+            pattern: None,
+            time_trigger: None,
+        };
+        assert!(!policy.should_roll_for_time(at(0, 0), at(1_000_000, 0)));
+    }
+
+    #[test]
+    fn should_roll_for_time_fires_once_now_reaches_the_boundary() {
+        let policy = Policy {
+            maximum_file_size: "10mb".to_string(),
+            max_size_roll_backups: 1,
+            pattern: None,
+            time_trigger: Some(TimeTrigger::Hourly),
+        };
+        let first_write = at(0, 0);
+        assert!(!policy.should_roll_for_time(first_write, at(3599, 0)));
+        assert!(policy.should_roll_for_time(first_write, at(3600, 0)));
+    }
 }
@@ -2,11 +2,11 @@
 use core::fmt;
 use std::{
     borrow::Cow,
+    collections::HashSet,
     io,
 };
 
 use fmtorp::Fmtr;
-use once_cell::sync::Lazy;
 use tracing::{
     field::Visit,
     metadata::LevelFilter,
@@ -37,6 +37,7 @@ use tracing_subscriber::{
         Layered,
     },
     prelude::__tracing_subscriber_SubscriberExt,
+    registry::LookupSpan,
     Layer,
 };
 
@@ -48,18 +49,22 @@ use crate::{
     appenders::Appenders,
     config::{
         AppenderId,
+        Casing,
+        FieldNames,
         Format as ConfigFormat,
         Target,
+        TimestampFormat,
     },
 };
 
-static NORMAL_FMT: Lazy<Format<Full, UtcOffsetTime>> =
-    Lazy::new(|| Format::default().with_timer(UtcOffsetTime).with_ansi(false));
-
 pub struct Logger<N = DefaultFields, F = EventFormatter> {
-    level:  LevelFilter,
-    target: Option<Target>,
-    layer:  Layered<FmtLayer<SpanBroker, N, F, BoxMakeWriter>, SpanBroker>,
+    level:               LevelFilter,
+    target:              Option<Target>,
+    /// Whether `format` is `EventFormatter::Json`, which is the only variant
+    /// that reads `JsonSpanFields`. Lets `on_new_span`/`on_record` skip
+    /// building that map entirely for the common non-json case.
+    captures_json_spans: bool,
+    layer:               Layered<FmtLayer<SpanBroker, N, F, BoxMakeWriter>, SpanBroker>,
 }
 impl Logger {
     pub fn new_erased<'a>(
@@ -69,6 +74,7 @@ impl Logger {
         ids: impl IntoIterator<Item = &'a AppenderId>,
         appenders: &Appenders,
         format: EventFormatter,
+        ansi_appenders: &HashSet<AppenderId>,
     ) -> PolyLayer<SpanBroker> {
         Box::new(Self::new(
             r,
@@ -77,6 +83,7 @@ impl Logger {
             ids.into_iter(),
             appenders,
             format,
+            ansi_appenders,
         ))
     }
 
@@ -107,6 +114,24 @@ impl Logger {
         accumulated_makewriter
     }
 
+    /// Whether a logger targeting `ids` may render with ANSI color codes,
+    /// given the set of appender ids configured as
+    /// `Appender::Console { ansi: true }`. Since a single logger's targets
+    /// are merged into one writer and rendered through one formatter, ANSI
+    /// is only safe to enable when *every* appender this logger writes to is
+    /// one of `ansi_appenders` -- otherwise the same colorized bytes would
+    /// land in a file sink too. A logger that mixes an ansi console with a
+    /// file (or a plain console) therefore renders uncolored, rather than
+    /// leaking escape codes into the file.
+    fn resolve_ansi<'a>(
+        ids: impl Iterator<Item = &'a AppenderId>,
+        ansi_appenders: &HashSet<AppenderId>,
+    ) -> bool {
+        let mut any = false;
+        let all_ansi = ids.inspect(|_| any = true).all(|id| ansi_appenders.contains(id));
+        any && all_ansi
+    }
+
     pub fn new<'a>(
         r: SpanBroker,
         level: LevelFilter,
@@ -114,17 +139,25 @@ impl Logger {
         ids: impl Iterator<Item = &'a AppenderId>,
         appenders: &Appenders,
         format: EventFormatter,
+        ansi_appenders: &HashSet<AppenderId>,
     ) -> Self {
-        let writer =
-            Self::mk_writer(ids, appenders).unwrap_or_else(|| BoxMakeWriter::new(io::sink));
+        let ids: Vec<&'a AppenderId> = ids.collect();
+        let ansi = Self::resolve_ansi(ids.iter().copied(), ansi_appenders);
+        let captures_json_spans = matches!(format, EventFormatter::Json(_));
+
+        let writer = Self::mk_writer(ids.into_iter(), appenders)
+            .unwrap_or_else(|| BoxMakeWriter::new(io::sink));
 
-        let fmt_layer = FmtLayer::default().event_format(format).with_ansi(false);
+        let fmt_layer = FmtLayer::default()
+            .event_format(format.with_ansi(ansi))
+            .with_ansi(false);
         let append_layer = fmt_layer.with_writer(writer);
         let layer = r.with(append_layer);
 
         Self {
             level,
             target,
+            captures_json_spans,
             layer,
         }
     }
@@ -134,23 +167,81 @@ impl Layer<SpanBroker> for Logger {
         Logger::is_enabled(self, meta)
     }
 
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attrs<'_>,
+        id: &tracing::span::Id,
+        ctx: Context<'_, SpanBroker>,
+    ) {
+        if !self.captures_json_spans {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            let mut fields = serde_json::Map::new();
+            attrs.record(&mut JsonFieldVisitor { map: &mut fields });
+            span.extensions_mut().insert(JsonSpanFields(fields));
+        }
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: Context<'_, SpanBroker>,
+    ) {
+        if !self.captures_json_spans {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            let mut ext = span.extensions_mut();
+            if let Some(JsonSpanFields(fields)) = ext.get_mut::<JsonSpanFields>() {
+                values.record(&mut JsonFieldVisitor { map: fields });
+            }
+        }
+    }
+
     fn on_event(&self, event: &Event<'_>, ctx: Context<'_, SpanBroker>) {
         self.layer.on_event(event, ctx);
     }
 }
 
 pub enum EventFormatter {
-    Normal,
+    Normal(Format<Full, TimeSource>),
     MessageOnly,
+    Json(JsonFormatter),
     Custom(CustomFormatter),
 }
 
-impl From<ConfigFormat> for EventFormatter {
-    fn from(f: ConfigFormat) -> Self {
-        match f {
-            ConfigFormat::Normal => Self::Normal,
+impl EventFormatter {
+    pub fn new(
+        format: ConfigFormat,
+        timestamp: TimestampFormat,
+        field_names: FieldNames,
+        level_casing: Casing,
+    ) -> Self {
+        let time_source = TimeSource::new(timestamp);
+        match format {
+            ConfigFormat::Normal => {
+                Self::Normal(Format::default().with_timer(time_source).with_ansi(false))
+            },
             ConfigFormat::MessageOnly => Self::MessageOnly,
-            ConfigFormat::Custom(s) => Self::Custom(CustomFormatter::new(s)),
+            ConfigFormat::Json => {
+                Self::Json(JsonFormatter::new(time_source, field_names, level_casing))
+            },
+            ConfigFormat::Custom(s) => {
+                Self::Custom(CustomFormatter::new(s, time_source, level_casing))
+            },
+        }
+    }
+
+    /// Enables level-by-color output for the `Normal` and `Custom` formats.
+    /// Console appenders opt in via `config::Appender::Console { ansi }`;
+    /// file appenders never pass `true` so their output stays plain.
+    pub fn with_ansi(self, ansi: bool) -> Self {
+        match self {
+            Self::Normal(fmt) => Self::Normal(fmt.with_ansi(ansi)),
+            Self::Custom(fmtr) => Self::Custom(fmtr.with_ansi(ansi)),
+            other => other,
         }
     }
 }
@@ -164,12 +255,13 @@ impl FormatEvent<SpanBroker, DefaultFields> for EventFormatter {
     ) -> std::fmt::Result {
         match self {
             Self::Custom(fmtr) => fmtr.format_event(ctx, writer, event),
+            Self::Json(fmtr) => fmtr.format_event(ctx, writer, event),
             Self::MessageOnly => {
                 let mut vs = SingleFieldVisitor::new(writer, MESSAGE_FIELD_NAME);
                 event.record(&mut vs);
                 Ok(())
             },
-            Self::Normal => NORMAL_FMT.format_event(ctx, writer, event),
+            Self::Normal(fmtr) => fmtr.format_event(ctx, writer, event),
         }
     }
 }
@@ -181,12 +273,15 @@ mod fields {
     pub const LEVEL: &str = "l";
 }
 
-struct CustomValueWriter<'ctx, 'evt> {
-    fmtr:  Fmtr<'static>,
-    ctx:   &'ctx FmtContext<'ctx, SpanBroker, DefaultFields>,
-    event: &'evt Event<'evt>,
+struct CustomValueWriter<'ctx, 'evt, 'ts> {
+    fmtr:         Fmtr<'static>,
+    ctx:          &'ctx FmtContext<'ctx, SpanBroker, DefaultFields>,
+    event:        &'evt Event<'evt>,
+    time_source:  &'ts TimeSource,
+    level_casing: Casing,
+    ansi:         bool,
 }
-impl<'ctx, 'evt> CustomValueWriter<'ctx, 'evt> {
+impl<'ctx, 'evt, 'ts> CustomValueWriter<'ctx, 'evt, 'ts> {
     fn write(&mut self, mut writer: Writer<'_>) -> fmt::Result {
         self.fmtr.write(&mut writer, self)
     }
@@ -195,7 +290,7 @@ impl<'ctx, 'evt> CustomValueWriter<'ctx, 'evt> {
         self.fmtr.field_from_name(s)
     }
 }
-impl<'ctx, 'evt> fmtorp::FieldValueWriter for CustomValueWriter<'ctx, 'evt> {
+impl<'ctx, 'evt, 'ts> fmtorp::FieldValueWriter for CustomValueWriter<'ctx, 'evt, 'ts> {
     fn write_value(&self, writer: &mut impl fmt::Write, field: fmtorp::Field) -> fmt::Result {
         let normalized_meta = self.event.normalized_metadata();
         let meta = normalized_meta
@@ -205,7 +300,7 @@ impl<'ctx, 'evt> fmtorp::FieldValueWriter for CustomValueWriter<'ctx, 'evt> {
         let id = field.id();
 
         if id == self.get_field_id(fields::TIMESTAMP) {
-            self.format_timestamp(&mut writer)?;
+            self.time_source.write_to(writer)?;
         } else if id == self.get_field_id(fields::TARGET) {
             write!(writer, "{}", meta.target())?;
         } else if id == self.get_field_id(fields::MESSAGE) {
@@ -217,21 +312,55 @@ impl<'ctx, 'evt> fmtorp::FieldValueWriter for CustomValueWriter<'ctx, 'evt> {
         } else if id == self.get_field_id(fields::FIELDS) {
             self.ctx.format_fields(writer.by_ref(), self.event)?;
         } else if id == self.get_field_id(fields::LEVEL) {
-            write!(writer, "{}", meta.level())?;
+            let text = self.level_casing.apply(&meta.level().to_string());
+            if self.ansi {
+                write!(writer, "{}", colorize_level(*meta.level(), &text))?;
+            } else {
+                write!(writer, "{text}")?;
+            }
         }
         Ok(())
     }
 }
+
+/// Wraps `text` in the ANSI SGR code for `level`'s severity, resetting
+/// formatting afterwards so the rest of the line is left uncolored.
+fn colorize_level(level: tracing::Level, text: &str) -> String {
+    let code = match level {
+        tracing::Level::ERROR => "31",
+        tracing::Level::WARN => "33",
+        tracing::Level::INFO => "32",
+        tracing::Level::DEBUG => "34",
+        tracing::Level::TRACE => "2",
+    };
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
 /// EAS: Follow strat from NORMAL_FMT
 /// move Message only  and this to formatter.rs and utcoffsettime
 pub struct CustomFormatter {
-    fmtr: fmtorp::Fmtr<'static>,
+    fmtr:         fmtorp::Fmtr<'static>,
+    time_source:  TimeSource,
+    level_casing: Casing,
+    ansi:         bool,
 }
 impl CustomFormatter {
-    fn new(fmt_str: impl Into<Cow<'static, str>>) -> Self {
+    fn new(
+        fmt_str: impl Into<Cow<'static, str>>,
+        time_source: TimeSource,
+        level_casing: Casing,
+    ) -> Self {
         let fmtr = fmtorp::Fmtr::new(fmt_str);
 
-        Self { fmtr }
+        Self {
+            fmtr,
+            time_source,
+            level_casing,
+            ansi: false,
+        }
+    }
+
+    fn with_ansi(self, ansi: bool) -> Self {
+        Self { ansi, ..self }
     }
 
     fn format_event(
@@ -244,17 +373,124 @@ impl CustomFormatter {
             fmtr: &self.fmtr,
             ctx,
             event,
+            time_source: &self.time_source,
+            level_casing: self.level_casing,
+            ansi: self.ansi,
         };
         value_writer.write(writer)
     }
+}
+
+/// Span fields recorded as structured JSON, stashed in the span's extensions
+/// so the `Json` formatter can include them alongside an event's own fields.
+struct JsonSpanFields(serde_json::Map<String, serde_json::Value>);
+
+/// Records a `tracing` field set into a `serde_json::Map`, per-type where
+/// possible and falling back to `{:?}` otherwise.
+struct JsonFieldVisitor<'a> {
+    map: &'a mut serde_json::Map<String, serde_json::Value>,
+}
+impl<'a> Visit for JsonFieldVisitor<'a> {
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.map
+            .insert(field.name().to_string(), serde_json::Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.map
+            .insert(field.name().to_string(), serde_json::Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.map
+            .insert(field.name().to_string(), serde_json::Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.map
+            .insert(field.name().to_string(), serde_json::Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.map
+            .insert(field.name().to_string(), serde_json::Value::from(value));
+    }
+
+    #[allow(clippy::use_debug)]
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        self.map.insert(
+            field.name().to_string(),
+            serde_json::Value::from(format!("{value:?}")),
+        );
+    }
+}
 
-    #[inline]
-    fn format_timestamp(&self, writer: &mut Writer<'_>) -> fmt::Result {
-        let t = tracing_subscriber::fmt::time::SystemTime;
-        if let Err(_) = t.format_time(writer) {
-            writer.write_str("<unknown time>")?;
+/// Formats events as newline-delimited JSON (NDJSON): one object per event
+/// with `timestamp`, `level`, `target`, `message`, plus a nested `fields`
+/// object holding the event's own fields merged over the current span
+/// fields.
+pub struct JsonFormatter {
+    time_source:  TimeSource,
+    field_names:  FieldNames,
+    level_casing: Casing,
+}
+impl JsonFormatter {
+    fn new(time_source: TimeSource, field_names: FieldNames, level_casing: Casing) -> Self {
+        Self {
+            time_source,
+            field_names,
+            level_casing,
         }
-        Ok(())
+    }
+
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, SpanBroker, DefaultFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let normalized_meta = event.normalized_metadata();
+        let meta = normalized_meta.as_ref().unwrap_or_else(|| event.metadata());
+
+        let mut fields = serde_json::Map::new();
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let ext = span.extensions();
+                if let Some(JsonSpanFields(span_fields)) = ext.get::<JsonSpanFields>() {
+                    fields.extend(span_fields.clone());
+                }
+            }
+        }
+        event.record(&mut JsonFieldVisitor { map: &mut fields });
+
+        let message = fields
+            .get(MESSAGE_FIELD_NAME)
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let level = self.level_casing.apply(&meta.level().to_string());
+
+        let rendered = self.time_source.render();
+        let timestamp = match self.time_source {
+            TimeSource::UnixSeconds | TimeSource::UnixMillis => rendered
+                .parse::<i64>()
+                .map_or_else(|_| serde_json::Value::from(rendered.clone()), serde_json::Value::from),
+            _ => serde_json::Value::from(rendered),
+        };
+
+        let mut record = serde_json::Map::new();
+        record.insert(self.field_names.timestamp.clone(), timestamp);
+        record.insert(self.field_names.level.clone(), serde_json::Value::from(level));
+        record.insert(
+            self.field_names.target.clone(),
+            serde_json::Value::from(meta.target()),
+        );
+        record.insert(self.field_names.message.clone(), serde_json::Value::from(message));
+        record.insert("fields".to_string(), serde_json::Value::Object(fields));
+
+        write!(writer, "{}", serde_json::Value::Object(record))?;
+        writeln!(writer)
     }
 }
 
@@ -290,14 +526,99 @@ impl<'w> Visit for SingleFieldVisitor<'w> {
 const TIME_FORMAT: time::format_description::well_known::Rfc3339 =
     time::format_description::well_known::Rfc3339;
 
-struct UtcOffsetTime;
+/// The timestamp representation a `Logger` renders on each event, built once
+/// from the logger's `config::TimestampFormat`.
+pub enum TimeSource {
+    Rfc3339,
+    UnixSeconds,
+    UnixMillis,
+    /// `None` when the configured pattern failed to parse; formatting then
+    /// always falls back to `<unknown time>`. `OwnedFormatItem` (rather than
+    /// `Vec<FormatItem<'_>>`) is required here since the borrowed form can't
+    /// outlive the `String` pattern it's parsed from.
+    Custom(Option<time::format_description::OwnedFormatItem>),
+}
+impl TimeSource {
+    fn new(format: TimestampFormat) -> Self {
+        match format {
+            TimestampFormat::Rfc3339 => Self::Rfc3339,
+            TimestampFormat::UnixSeconds => Self::UnixSeconds,
+            TimestampFormat::UnixMillis => Self::UnixMillis,
+            TimestampFormat::Custom(pattern) => {
+                Self::Custom(time::format_description::parse_owned::<2>(&pattern).ok())
+            },
+        }
+    }
+
+    fn now() -> time::OffsetDateTime {
+        time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc())
+    }
+
+    /// Writes the current timestamp using an ad hoc `fmt::Write`, for
+    /// contexts (like `CustomValueWriter`) that aren't already holding a
+    /// `Writer`.
+    fn write_to(&self, writer: &mut impl fmt::Write) -> fmt::Result {
+        let mut w = Writer::new(writer);
+        self.format_time(&mut w)
+    }
 
-impl FormatTime for UtcOffsetTime {
+    /// Renders the current timestamp to an owned `String`, for contexts (like
+    /// the `Json` formatter) that build a value rather than writing directly.
+    fn render(&self) -> String {
+        let mut buf = String::new();
+        let _ = self.write_to(&mut buf);
+        buf
+    }
+}
+impl FormatTime for TimeSource {
     fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
-        let ts =
-            time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc());
-        let ts_str = ts.format(&TIME_FORMAT).unwrap_or_default();
+        match self {
+            Self::Rfc3339 => {
+                let ts_str = Self::now().format(&TIME_FORMAT).unwrap_or_default();
+                w.write_str(&ts_str)
+            },
+            Self::UnixSeconds => write!(w, "{}", Self::now().unix_timestamp()),
+            Self::UnixMillis => write!(w, "{}", Self::now().unix_timestamp_nanos() / 1_000_000),
+            Self::Custom(Some(items)) => match Self::now().format(items) {
+                Ok(ts_str) => w.write_str(&ts_str),
+                Err(_) => w.write_str("<unknown time>"),
+            },
+            Self::Custom(None) => w.write_str("<unknown time>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_ansi_true_when_every_target_is_ansi() {
+        let console: AppenderId = "console".into();
+        let ansi_appenders = HashSet::from([console.clone()]);
+
+        assert!(Logger::resolve_ansi([&console].into_iter(), &ansi_appenders));
+    }
+
+    #[test]
+    fn resolve_ansi_false_when_mixed_with_a_non_ansi_sink() {
+        let console: AppenderId = "console".into();
+        let file: AppenderId = "file".into();
+        let ansi_appenders = HashSet::from([console.clone()]);
+
+        assert!(!Logger::resolve_ansi(
+            [&console, &file].into_iter(),
+            &ansi_appenders
+        ));
+    }
+
+    #[test]
+    fn resolve_ansi_false_when_no_targets() {
+        let ansi_appenders = HashSet::new();
 
-        w.write_str(&ts_str)
+        assert!(!Logger::resolve_ansi(
+            std::iter::empty::<&AppenderId>(),
+            &ansi_appenders
+        ));
     }
 }